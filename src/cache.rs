@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+
+use alloy_primitives::{keccak256, B256, U256};
+use alloy_trie::{HashBuilder, Nibbles};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+use crate::common::{code_hash_of, encode_account, extract_account_fields, extract_address, EMPTY_ROOT};
+use crate::trie::StorageTrie;
+
+/// The cached state for a single account: the fields needed to rebuild its
+/// trie leaf, plus the storage snapshot used to detect whether its storage
+/// trie changed since the last call.
+#[derive(Clone)]
+struct StoredAccount {
+    nonce: u64,
+    balance: U256,
+    code_hash: B256,
+    storage_root: B256,
+    storage_snapshot: Vec<(B256, U256)>,
+    leaf_rlp: Vec<u8>,
+}
+
+/// An accelerator for repeated `state_root` calls against the same
+/// (mostly-unchanged) `State` object.
+///
+/// Modeled on the overlay/cache pattern used by clients like OpenEthereum's
+/// `Account` storage overlay and Nimbus's `AccountsCache`: each call diffs
+/// the incoming state against the cached fields and storage snapshot for
+/// every hashed address, only recomputing the storage roots and account
+/// leaves that actually changed, then rebuilds the `HashBuilder` from the
+/// full sorted leaf set held in the cache. Every call still reads every
+/// account and every storage slot out of Python, hashes and sorts each
+/// slot for the comparison, and rebuilds the account trie over all leaves,
+/// so its cost stays linear in the total state; what it skips for
+/// unchanged accounts is storage-trie hashing and leaf encoding.
+#[pyclass]
+pub(crate) struct StateRootCache {
+    accounts: HashMap<B256, StoredAccount>,
+}
+
+#[pymethods]
+impl StateRootCache {
+    #[new]
+    fn new() -> Self {
+        Self {
+            accounts: HashMap::new(),
+        }
+    }
+
+    /// Compute the state root for `state`, reusing cached account leaves
+    /// and storage roots for every address whose data is unchanged since
+    /// the previous call.
+    fn state_root(&mut self, state: &Bound<'_, PyAny>) -> PyResult<Py<PyBytes>> {
+        let main_trie = state.getattr("_main_trie")?;
+        let storage_tries = state.getattr("_storage_tries")?;
+
+        let snapshots = state.getattr("_snapshots")?;
+        let snapshots_len: usize = snapshots.len()?;
+        if snapshots_len > 0 {
+            return Err(pyo3::exceptions::PyAssertionError::new_err(
+                "Cannot compute state root during a transaction",
+            ));
+        }
+
+        let py = state.py();
+        let trie_data = main_trie.getattr("_data")?;
+        let trie_dict = trie_data.downcast::<PyDict>()?;
+
+        let mut seen: HashSet<B256> = HashSet::with_capacity(trie_dict.len());
+
+        for (address_bytes, account_obj) in trie_dict.iter() {
+            if account_obj.is_none() {
+                continue;
+            }
+
+            let address_py_bytes = address_bytes.downcast::<PyBytes>()?;
+            let Some(address) = extract_address(&address_py_bytes) else {
+                continue;
+            };
+            let hashed_address = keccak256(address);
+            seen.insert(hashed_address);
+
+            let (nonce, balance, code) = extract_account_fields(&account_obj)?;
+            let code_hash = code_hash_of(&code);
+
+            let storage_entries =
+                StorageTrie::snapshot_raw_from_storage_tries(&storage_tries, &address_py_bytes)?;
+            let storage_trie = StorageTrie::from_entries(storage_entries);
+            let storage_snapshot = storage_trie.entries().to_vec();
+
+            let existing = self.accounts.get(&hashed_address);
+            let storage_unchanged = existing
+                .map(|cached| cached.storage_snapshot == storage_snapshot)
+                .unwrap_or(false);
+
+            let storage_root = if storage_unchanged {
+                existing.unwrap().storage_root
+            } else {
+                storage_trie.root()
+            };
+
+            let account_unchanged = storage_unchanged
+                && existing
+                    .map(|cached| {
+                        cached.nonce == nonce
+                            && cached.balance == balance
+                            && cached.code_hash == code_hash
+                    })
+                    .unwrap_or(false);
+
+            let leaf_rlp = if account_unchanged {
+                existing.unwrap().leaf_rlp.clone()
+            } else {
+                encode_account(nonce, balance, storage_root, code_hash)
+            };
+
+            self.accounts.insert(
+                hashed_address,
+                StoredAccount {
+                    nonce,
+                    balance,
+                    code_hash,
+                    storage_root,
+                    storage_snapshot,
+                    leaf_rlp,
+                },
+            );
+        }
+
+        // Drop accounts that no longer exist in state.
+        self.accounts.retain(|hashed_address, _| seen.contains(hashed_address));
+
+        if self.accounts.is_empty() {
+            return Ok(PyBytes::new_bound(py, EMPTY_ROOT.as_slice()).into());
+        }
+
+        let mut entries: Vec<(&B256, &StoredAccount)> = self.accounts.iter().collect();
+        entries.sort_by_key(|(hashed_address, _)| **hashed_address);
+
+        let mut hash_builder = HashBuilder::default();
+        for (hashed_address, stored) in entries {
+            hash_builder.add_leaf(Nibbles::unpack(*hashed_address), &stored.leaf_rlp);
+        }
+
+        let root = hash_builder.root();
+        Ok(PyBytes::new_bound(py, root.as_slice()).into())
+    }
+
+    /// Drop all cached account state, forcing the next `state_root` call to
+    /// recompute everything from scratch.
+    fn clear(&mut self) {
+        self.accounts.clear();
+    }
+
+    fn __len__(&self) -> usize {
+        self.accounts.len()
+    }
+}
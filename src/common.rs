@@ -0,0 +1,205 @@
+//! Shared building blocks for every trie/state function in this crate:
+//! the empty-root/empty-code-hash constants, the account RLP encoding,
+//! Python account-field extraction, and the generic "sort leaves by
+//! hashed key, fold into a `HashBuilder`" step used by both the account
+//! trie and every storage trie.
+//!
+//! Centralizing this here means a fix to the trie-building algorithm (or
+//! to malformed-entry handling) only has to happen once.
+
+use alloy_primitives::{keccak256, Address, B256, U256};
+use alloy_rlp::{Encodable, RlpEncodable};
+use alloy_trie::proof::{ProofNodes, ProofRetainer};
+use alloy_trie::{HashBuilder, Nibbles};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+/// Empty trie root: `keccak256(rlp(""))`.
+pub(crate) const EMPTY_ROOT: B256 = B256::new([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6,
+    0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0,
+    0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// `keccak256` of empty bytecode.
+pub(crate) const EMPTY_CODE_HASH: B256 = B256::new([
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c,
+    0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b,
+    0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+]);
+
+#[derive(RlpEncodable)]
+struct TempAccount<'a> {
+    nonce: u64,
+    balance: U256,
+    storage_root: &'a [u8; 32],
+    code_hash: &'a [u8; 32],
+}
+
+/// RLP-encode an account leaf as `[nonce, balance, storage_root, code_hash]`.
+pub(crate) fn encode_account(nonce: u64, balance: U256, storage_root: B256, code_hash: B256) -> Vec<u8> {
+    let temp_account = TempAccount {
+        nonce,
+        balance,
+        storage_root: storage_root.as_ref(),
+        code_hash: code_hash.as_ref(),
+    };
+    let mut out = Vec::new();
+    temp_account.encode(&mut out);
+    out
+}
+
+/// `keccak256(code)`, or [`EMPTY_CODE_HASH`] for empty code.
+pub(crate) fn code_hash_of(code: &[u8]) -> B256 {
+    if code.is_empty() {
+        EMPTY_CODE_HASH
+    } else {
+        keccak256(code)
+    }
+}
+
+/// Decode a 20-byte address key, skipping (returning `None` for) anything
+/// malformed. Every trie walk in this crate treats a wrong-width key the
+/// same way: skip rather than fail the whole computation.
+pub(crate) fn extract_address(address_py_bytes: &Bound<'_, PyBytes>) -> Option<Address> {
+    let addr_bytes = address_py_bytes.as_bytes();
+    if addr_bytes.len() != 20 {
+        return None;
+    }
+    let mut addr_array = [0u8; 20];
+    addr_array.copy_from_slice(addr_bytes);
+    Some(Address::from(addr_array))
+}
+
+/// Decode a 32-byte storage slot key, skipping (returning `None` for)
+/// anything malformed.
+pub(crate) fn extract_slot(slot_py_bytes: &Bound<'_, PyBytes>) -> Option<B256> {
+    let slot_bytes = slot_py_bytes.as_bytes();
+    if slot_bytes.len() != 32 {
+        return None;
+    }
+    Some(B256::from_slice(slot_bytes))
+}
+
+/// Encode `value` as a minimal big-endian quantity, the way `eth_getProof`
+/// reports `balance` and `storageProof[].value`: no leading zero bytes, and
+/// a single `0x00` byte for zero (never RLP, which is reserved for trie
+/// leaves).
+pub(crate) fn u256_to_quantity_bytes(value: U256) -> Vec<u8> {
+    let be = value.to_be_bytes::<32>();
+    let first_nonzero = be.iter().position(|&b| b != 0);
+    match first_nonzero {
+        Some(i) => be[i..].to_vec(),
+        None => vec![0u8],
+    }
+}
+
+/// Pull `nonce`, `balance`, and `code` off a Python `Account` object.
+pub(crate) fn extract_account_fields(account: &Bound<'_, PyAny>) -> PyResult<(u64, U256, Vec<u8>)> {
+    let nonce: u64 = account.getattr("nonce")?.extract()?;
+
+    let balance_obj = account.getattr("balance")?;
+    let balance_bytes: Vec<u8> = balance_obj.call_method0("to_be_bytes32")?.extract()?;
+    let balance = U256::from_be_slice(&balance_bytes);
+
+    let code: Vec<u8> = account.getattr("code")?.extract()?;
+
+    Ok((nonce, balance, code))
+}
+
+/// Look up `address_bytes`'s storage trie inside a `state._storage_tries`
+/// dict and return its `_data` dict, if the address has one.
+pub(crate) fn lookup_storage_data<'py>(
+    storage_tries: &Bound<'py, PyAny>,
+    address_bytes: &Bound<'py, PyBytes>,
+) -> PyResult<Option<Bound<'py, PyDict>>> {
+    let storage_dict = storage_tries.downcast::<PyDict>()?;
+    let Some(trie) = storage_dict.get_item(address_bytes)? else {
+        return Ok(None);
+    };
+    let trie_data = trie.getattr("_data")?;
+    Ok(Some(trie_data.downcast::<PyDict>()?.clone()))
+}
+
+/// Sort `leaves` by `hashed(key)` and fold them into a fresh
+/// `HashBuilder`, returning the resulting secured-trie root. Shared by
+/// both the account trie and every storage trie.
+pub(crate) fn build_trie_root<K: Copy>(
+    mut leaves: Vec<(K, Vec<u8>)>,
+    hashed: impl Fn(K) -> B256,
+) -> B256 {
+    if leaves.is_empty() {
+        return EMPTY_ROOT;
+    }
+
+    leaves.sort_by_key(|(key, _)| hashed(*key));
+
+    let mut hash_builder = HashBuilder::default();
+    for (key, leaf) in leaves {
+        hash_builder.add_leaf(Nibbles::unpack(hashed(key)), &leaf);
+    }
+    hash_builder.root()
+}
+
+/// Same as [`build_trie_root`], but installs a `ProofRetainer` over
+/// `targets` first and returns the retained proof nodes alongside the
+/// root.
+pub(crate) fn build_trie_root_with_proof<K: Copy>(
+    mut leaves: Vec<(K, Vec<u8>)>,
+    hashed: impl Fn(K) -> B256,
+    targets: Vec<Nibbles>,
+) -> (B256, ProofNodes) {
+    leaves.sort_by_key(|(key, _)| hashed(*key));
+
+    let mut hash_builder = HashBuilder::default().with_proof_retainer(ProofRetainer::new(targets));
+    for (key, leaf) in leaves {
+        hash_builder.add_leaf(Nibbles::unpack(hashed(key)), &leaf);
+    }
+    let root = hash_builder.root();
+    let proof_nodes = hash_builder.take_proof_nodes();
+    (root, proof_nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_trie_has_empty_root() {
+        let leaves: Vec<(Address, Vec<u8>)> = Vec::new();
+        assert_eq!(build_trie_root(leaves, keccak256), EMPTY_ROOT);
+    }
+
+    #[test]
+    fn trie_root_is_order_independent() {
+        let a = (Address::with_last_byte(1), vec![1, 2, 3]);
+        let b = (Address::with_last_byte(2), vec![4, 5, 6]);
+
+        let forward = build_trie_root(vec![a.clone(), b.clone()], keccak256);
+        let reversed = build_trie_root(vec![b, a], keccak256);
+        assert_eq!(forward, reversed);
+    }
+
+    #[test]
+    fn quantity_bytes_strip_leading_zeros() {
+        assert_eq!(u256_to_quantity_bytes(U256::ZERO), vec![0u8]);
+        assert_eq!(u256_to_quantity_bytes(U256::from(1u64)), vec![1u8]);
+        assert_eq!(u256_to_quantity_bytes(U256::from(0x0100u64)), vec![1u8, 0u8]);
+    }
+
+    #[test]
+    fn proof_nodes_retain_target_path() {
+        let a = (Address::with_last_byte(1), vec![1, 2, 3]);
+        let b = (Address::with_last_byte(2), vec![4, 5, 6]);
+        let target = Nibbles::unpack(keccak256(a.0));
+
+        let (root, proof_nodes) = build_trie_root_with_proof(vec![a, b], keccak256, vec![target.clone()]);
+        assert_eq!(root, build_trie_root(
+            vec![(Address::with_last_byte(1), vec![1, 2, 3]), (Address::with_last_byte(2), vec![4, 5, 6])],
+            keccak256,
+        ));
+        assert!(!proof_nodes.matching_nodes_sorted(&target).is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+
+use alloy_primitives::{keccak256, Address, B256, U256};
+use alloy_rlp::{Encodable, RlpEncodable};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+use crate::common::{extract_account_fields, extract_address, EMPTY_CODE_HASH};
+use crate::trie::StorageTrie;
+
+/// `code_flag` discriminant for [`SnapshotAccount`], mirroring the
+/// OpenEthereum snapshot account layout.
+const CODE_EMPTY: u8 = 0;
+const CODE_INLINE: u8 = 1;
+const CODE_HASH_REF: u8 = 2;
+
+#[derive(RlpEncodable)]
+struct StoragePair {
+    hashed_slot: [u8; 32],
+    value_rlp: Vec<u8>,
+}
+
+/// A "fat" account entry: storage is inlined as sorted `[hashed_slot,
+/// rlp(value)]` pairs instead of being collapsed into a 32-byte storage
+/// root, and code is either omitted (empty), embedded inline the first
+/// time it's seen, or referenced by hash on every later account that
+/// shares it.
+#[derive(RlpEncodable)]
+struct SnapshotAccount {
+    hashed_address: [u8; 32],
+    nonce: u64,
+    balance: U256,
+    code_flag: u8,
+    code_payload: Vec<u8>,
+    storage: Vec<StoragePair>,
+}
+
+/// Snapshot a single address's storage trie as sorted `[hashed_slot,
+/// rlp(value)]` pairs, skipping deleted (zero) and malformed entries.
+fn snapshot_storage(
+    storage_tries: &Bound<'_, PyAny>,
+    address_bytes: &Bound<'_, PyBytes>,
+) -> PyResult<Vec<StoragePair>> {
+    let storage_trie = StorageTrie::from_storage_tries(storage_tries, address_bytes)?;
+
+    Ok(storage_trie
+        .entries()
+        .iter()
+        .map(|(slot, value)| {
+            let hashed_slot = keccak256(slot);
+            let mut value_rlp = Vec::new();
+            value.encode(&mut value_rlp);
+            StoragePair {
+                hashed_slot: hashed_slot.0,
+                value_rlp,
+            }
+        })
+        .collect())
+}
+
+/// Export the whole state into the "fat account" snapshot format used by
+/// fast-sync clients.
+///
+/// Unlike `state_root`, which collapses each account's storage into a
+/// 32-byte storage root, this inlines the storage directly as sorted
+/// `[hashed_slot, rlp(value)]` pairs, and code is embedded inline (once
+/// per distinct bytecode) or referenced by hash, so the returned blob is
+/// self-contained: both the full state and its root can be reconstructed
+/// from it without the original Python `State` object.
+///
+/// Parameters
+/// ----------
+/// state : State
+///     The Python State object from ethereum.forks.{fork}.state
+///
+/// Returns
+/// -------
+/// snapshot : bytes
+///     An RLP-encoded list of accounts, sorted by `keccak256(address)`,
+///     each `[hashed_address, nonce, balance, code_flag, code_payload,
+///     storage_pairs]`
+#[pyfunction]
+pub(crate) fn state_snapshot(state: &Bound<'_, PyAny>) -> PyResult<Py<PyBytes>> {
+    let main_trie = state.getattr("_main_trie")?;
+    let storage_tries = state.getattr("_storage_tries")?;
+
+    let snapshots = state.getattr("_snapshots")?;
+    let snapshots_len: usize = snapshots.len()?;
+    if snapshots_len > 0 {
+        return Err(pyo3::exceptions::PyAssertionError::new_err(
+            "Cannot compute state root during a transaction",
+        ));
+    }
+
+    let py = state.py();
+    let trie_data = main_trie.getattr("_data")?;
+    let trie_dict = trie_data.downcast::<PyDict>()?;
+
+    let mut accounts: Vec<(Address, u64, U256, Vec<u8>)> = Vec::new();
+    for (address_bytes, account_obj) in trie_dict.iter() {
+        if account_obj.is_none() {
+            continue;
+        }
+
+        let address_py_bytes = address_bytes.downcast::<PyBytes>()?;
+        let Some(address) = extract_address(&address_py_bytes) else {
+            continue;
+        };
+
+        let (nonce, balance, code) = extract_account_fields(&account_obj)?;
+
+        accounts.push((address, nonce, balance, code));
+    }
+    accounts.sort_by_key(|(addr, _, _, _)| keccak256(*addr));
+
+    // Track which code hashes have already been emitted inline, so repeat
+    // occurrences (e.g. every clone of a popular contract) are referenced
+    // by hash instead of re-embedded.
+    let mut emitted_code: HashSet<B256> = HashSet::new();
+
+    let mut snapshot_accounts: Vec<SnapshotAccount> = Vec::with_capacity(accounts.len());
+    for (address, nonce, balance, code) in accounts {
+        let address_py_bytes = PyBytes::new_bound(py, address.as_slice());
+        let storage = snapshot_storage(&storage_tries, &address_py_bytes)?;
+
+        let (code_flag, code_payload) = if code.is_empty() {
+            (CODE_EMPTY, Vec::new())
+        } else {
+            let code_hash = keccak256(&code);
+            if code_hash == EMPTY_CODE_HASH {
+                (CODE_EMPTY, Vec::new())
+            } else if emitted_code.insert(code_hash) {
+                (CODE_INLINE, code)
+            } else {
+                (CODE_HASH_REF, code_hash.0.to_vec())
+            }
+        };
+
+        snapshot_accounts.push(SnapshotAccount {
+            hashed_address: keccak256(address).0,
+            nonce,
+            balance,
+            code_flag,
+            code_payload,
+            storage,
+        });
+    }
+
+    let mut out = Vec::new();
+    snapshot_accounts.encode(&mut out);
+
+    Ok(PyBytes::new_bound(py, &out).into())
+}
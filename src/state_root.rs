@@ -0,0 +1,129 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use alloy_primitives::{Address, B256, U256};
+use rayon::prelude::*;
+
+use crate::common::{code_hash_of, encode_account, extract_account_fields, extract_address, EMPTY_ROOT};
+use crate::trie::{AccountsTrie, StorageTrie};
+
+/// An account's fields snapshotted out of Python, plus its raw (unsorted,
+/// zero-filtered) storage entries. Owned and GIL-free, so it can cross into
+/// a rayon closure.
+struct AccountSnapshot {
+    address: Address,
+    nonce: u64,
+    balance: U256,
+    code: Vec<u8>,
+    storage_entries: Vec<(B256, U256)>,
+}
+
+/// GIL-free: compute an account's storage root and RLP-encoded trie leaf
+/// from its snapshotted fields.
+fn compute_account_leaf(account: AccountSnapshot) -> (Address, Vec<u8>) {
+    let storage_root = StorageTrie::from_entries(account.storage_entries).root();
+    let code_hash = code_hash_of(&account.code);
+    let leaf_rlp = encode_account(account.nonce, account.balance, storage_root, code_hash);
+    (account.address, leaf_rlp)
+}
+
+/// Compute the state root for a given Python State object
+///
+/// This function is designed to be a drop-in replacement for the Python
+/// `state_root(state: State) -> Root` function in ethereum.forks.{fork}.state
+///
+/// Parameters
+/// ----------
+/// state : State
+///     The Python State object from ethereum.forks.{fork}.state
+///
+/// Returns
+/// -------
+/// root : bytes
+///     The 32-byte state root hash
+#[pyfunction]
+pub(crate) fn state_root(state: &Bound<'_, PyAny>) -> PyResult<Py<PyBytes>> {
+    // Extract the _main_trie from the State object
+    let main_trie = state.getattr("_main_trie")?;
+
+    // Extract the _storage_tries from the State object
+    let storage_tries = state.getattr("_storage_tries")?;
+
+    // Check that there are no active snapshots (transactions)
+    let snapshots = state.getattr("_snapshots")?;
+    let snapshots_len: usize = snapshots.len()?;
+    if snapshots_len > 0 {
+        return Err(pyo3::exceptions::PyAssertionError::new_err(
+            "Cannot compute state root during a transaction"
+        ));
+    }
+
+    let py = state.py();
+
+    // Try to get the data from main_trie
+    // Note: The Python trie structure has a _data attribute that holds the accounts
+    let trie_data = main_trie.getattr("_data")?;
+    let trie_dict = trie_data.downcast::<PyDict>()?;
+
+    // If the trie is empty, return the empty trie root
+    if trie_dict.is_empty() {
+        return Ok(PyBytes::new_bound(py, EMPTY_ROOT.as_slice()).into());
+    }
+
+    // GIL-bound pass: snapshot every account's plain fields and its
+    // storage entries into owned Rust data. Nothing below this point holds
+    // a Python reference, so it's safe to cross into a GIL-free rayon map.
+    let mut snapshots: Vec<AccountSnapshot> = Vec::new();
+
+    for (address_bytes, account_obj) in trie_dict.iter() {
+        // Skip None accounts
+        if account_obj.is_none() {
+            continue;
+        }
+
+        let address_py_bytes = address_bytes.downcast::<PyBytes>()?;
+        let Some(address) = extract_address(&address_py_bytes) else {
+            continue; // Skip invalid addresses
+        };
+
+        // Extract account info
+        let (nonce, balance, code) = extract_account_fields(&account_obj)?;
+
+        // Snapshot this address's storage entries (still GIL-bound)
+        let storage_entries = StorageTrie::snapshot_raw_from_storage_tries(&storage_tries, &address_py_bytes)?;
+
+        snapshots.push(AccountSnapshot {
+            address,
+            nonce,
+            balance,
+            code,
+            storage_entries,
+        });
+    }
+
+    // GIL-free pass: storage roots are independent per account, so compute
+    // each account's storage root and RLP leaf in parallel with rayon.
+    let leaves: Vec<(Address, Vec<u8>)> = py.allow_threads(|| {
+        snapshots
+            .into_par_iter()
+            .map(compute_account_leaf)
+            .collect()
+    });
+
+    // Sort by hashed address (crucial for the trie) and feed the
+    // `HashBuilder` on this thread, since it isn't `Sync`.
+    let root = AccountsTrie::from_leaves(leaves).root();
+
+    // Convert B256 to bytes and return
+    Ok(PyBytes::new_bound(py, root.as_slice()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_imports() {
+        // Basic test to ensure the module compiles
+        assert!(true);
+    }
+}
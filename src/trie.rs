@@ -0,0 +1,232 @@
+use alloy_primitives::{keccak256, Address, B256, U256};
+use alloy_rlp::Encodable;
+use alloy_trie::proof::ProofNodes;
+use alloy_trie::Nibbles;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+use crate::common::{
+    build_trie_root, build_trie_root_with_proof, code_hash_of, encode_account, extract_address,
+    extract_slot, lookup_storage_data, EMPTY_ROOT,
+};
+
+/// A single account's secured storage trie: keys are always the
+/// `keccak256` of a 32-byte slot. This is the one place that walks a
+/// storage trie's `_data` dict — `state_root`, `state_proof`,
+/// `state_witness`, `StateRootCache`, and `state_snapshot` all build on
+/// top of it instead of re-reading `_data` themselves.
+pub(crate) struct StorageTrie {
+    /// `(slot, value)`, sorted by `keccak256(slot)`.
+    entries: Vec<(B256, U256)>,
+}
+
+impl StorageTrie {
+    /// Build from an already-snapshotted `(slot, value)` list (e.g. the
+    /// output of [`StorageTrie::snapshot_raw_entries`] handed across a
+    /// GIL-free rayon closure). Sorting happens here, so callers that want
+    /// the sort off the GIL should snapshot raw and construct later.
+    pub(crate) fn from_entries(mut entries: Vec<(B256, U256)>) -> Self {
+        entries.sort_by_key(|(slot, _)| keccak256(*slot));
+        Self { entries }
+    }
+
+    /// Snapshot a storage trie's `_data` dict (`slot: bytes -> value`)
+    /// into owned, *unsorted* `(slot, value)` pairs, skipping malformed
+    /// keys and deleted (zero-value) entries exactly as `state_root`
+    /// always has. GIL-bound; pair with [`StorageTrie::from_entries`] to
+    /// defer the sort/hash work off the GIL.
+    pub(crate) fn snapshot_raw_entries(trie_dict: &Bound<'_, PyDict>) -> PyResult<Vec<(B256, U256)>> {
+        let mut entries = Vec::new();
+        for (key_bytes, value_obj) in trie_dict.iter() {
+            if value_obj.is_none() {
+                continue;
+            }
+
+            let key_py_bytes = key_bytes.downcast::<PyBytes>()?;
+            let Some(slot) = extract_slot(&key_py_bytes) else {
+                continue;
+            };
+
+            let value_bytes: Vec<u8> = value_obj.call_method0("to_be_bytes32")?.extract()?;
+            let value = U256::from_be_slice(&value_bytes);
+            if value.is_zero() {
+                continue;
+            }
+
+            entries.push((slot, value));
+        }
+        Ok(entries)
+    }
+
+    /// Build from a storage trie's `_data` dict directly.
+    pub(crate) fn from_data(trie_dict: &Bound<'_, PyDict>) -> PyResult<Self> {
+        Ok(Self::from_entries(Self::snapshot_raw_entries(trie_dict)?))
+    }
+
+    /// Look up `address_bytes`'s raw, unsorted storage entries inside
+    /// `storage_tries`, or an empty list if the address has no storage.
+    pub(crate) fn snapshot_raw_from_storage_tries(
+        storage_tries: &Bound<'_, PyAny>,
+        address_bytes: &Bound<'_, PyBytes>,
+    ) -> PyResult<Vec<(B256, U256)>> {
+        match lookup_storage_data(storage_tries, address_bytes)? {
+            Some(trie_dict) => Self::snapshot_raw_entries(&trie_dict),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Look up `address_bytes`'s storage trie inside `storage_tries` and
+    /// build it, or an empty trie if the address has no storage.
+    pub(crate) fn from_storage_tries(
+        storage_tries: &Bound<'_, PyAny>,
+        address_bytes: &Bound<'_, PyBytes>,
+    ) -> PyResult<Self> {
+        Ok(Self::from_entries(Self::snapshot_raw_from_storage_tries(
+            storage_tries,
+            address_bytes,
+        )?))
+    }
+
+    /// The sorted `(slot, value)` entries backing this trie.
+    pub(crate) fn entries(&self) -> &[(B256, U256)] {
+        &self.entries
+    }
+
+    /// Consume the trie, returning its secured root.
+    pub(crate) fn root(self) -> B256 {
+        if self.entries.is_empty() {
+            return EMPTY_ROOT;
+        }
+        let leaves: Vec<(B256, Vec<u8>)> = self
+            .entries
+            .into_iter()
+            .map(|(slot, value)| {
+                let mut value_rlp = Vec::new();
+                value.encode(&mut value_rlp);
+                (slot, value_rlp)
+            })
+            .collect();
+        build_trie_root(leaves, keccak256)
+    }
+
+    /// Consume the trie, returning its root and the proof nodes retained
+    /// along `targets` (each a `Nibbles::unpack(keccak256(slot))` path).
+    pub(crate) fn root_with_proof(self, targets: Vec<Nibbles>) -> (B256, ProofNodes) {
+        if self.entries.is_empty() {
+            return (EMPTY_ROOT, ProofNodes::default());
+        }
+        let leaves: Vec<(B256, Vec<u8>)> = self
+            .entries
+            .into_iter()
+            .map(|(slot, value)| {
+                let mut value_rlp = Vec::new();
+                value.encode(&mut value_rlp);
+                (slot, value_rlp)
+            })
+            .collect();
+        build_trie_root_with_proof(leaves, keccak256, targets)
+    }
+}
+
+/// The main, secured account trie: keys are always the `keccak256` of a
+/// 20-byte address. Resolves each account's storage root through its own
+/// [`StorageTrie`] rather than reaching into `storage_tries` by hand.
+pub(crate) struct AccountsTrie {
+    /// `(address, rlp(account))`.
+    leaves: Vec<(Address, Vec<u8>)>,
+}
+
+impl AccountsTrie {
+    /// Build from already-encoded `(address, rlp(account))` leaves (e.g.
+    /// the output of a GIL-free rayon map).
+    pub(crate) fn from_leaves(leaves: Vec<(Address, Vec<u8>)>) -> Self {
+        Self { leaves }
+    }
+
+    /// Build from the main trie's `_data` dict (`address: bytes ->
+    /// Account`) and the state's `storage_tries` dict, skipping malformed
+    /// keys exactly as `state_root` always has.
+    pub(crate) fn from_data(
+        trie_dict: &Bound<'_, PyDict>,
+        storage_tries: &Bound<'_, PyAny>,
+    ) -> PyResult<Self> {
+        let mut leaves = Vec::new();
+        for (address_bytes, account_obj) in trie_dict.iter() {
+            if account_obj.is_none() {
+                continue;
+            }
+
+            let address_py_bytes = address_bytes.downcast::<PyBytes>()?;
+            let Some(address) = extract_address(&address_py_bytes) else {
+                continue;
+            };
+
+            let (nonce, balance, code) = crate::common::extract_account_fields(&account_obj)?;
+            let storage_root = StorageTrie::from_storage_tries(storage_tries, &address_py_bytes)?.root();
+            let code_hash = code_hash_of(&code);
+
+            leaves.push((address, encode_account(nonce, balance, storage_root, code_hash)));
+        }
+        Ok(Self { leaves })
+    }
+
+    /// Consume the trie, returning its secured root.
+    pub(crate) fn root(self) -> B256 {
+        build_trie_root(self.leaves, keccak256)
+    }
+
+    /// Consume the trie, returning its root and the proof nodes retained
+    /// along `targets` (each a `Nibbles::unpack(keccak256(address))` path).
+    pub(crate) fn root_with_proof(self, targets: Vec<Nibbles>) -> (B256, ProofNodes) {
+        build_trie_root_with_proof(self.leaves, keccak256, targets)
+    }
+}
+
+/// Compute the root of a single storage trie in isolation.
+///
+/// Parameters
+/// ----------
+/// storage_trie : Trie
+///     A single account's storage trie (e.g. `state._storage_tries[address]`)
+///
+/// Returns
+/// -------
+/// root : bytes
+///     The 32-byte storage root hash
+#[pyfunction]
+pub(crate) fn storage_root(storage_trie: &Bound<'_, PyAny>) -> PyResult<Py<PyBytes>> {
+    let py = storage_trie.py();
+
+    let trie_data = storage_trie.getattr("_data")?;
+    let trie_dict = trie_data.downcast::<PyDict>()?;
+
+    let root = StorageTrie::from_data(trie_dict)?.root();
+    Ok(PyBytes::new_bound(py, root.as_slice()).into())
+}
+
+/// Compute the root of the main account trie in isolation.
+///
+/// Parameters
+/// ----------
+/// main_trie : Trie
+///     The State object's main trie (`state._main_trie`)
+/// storage_tries : Dict[Address, Trie]
+///     The State object's per-address storage tries (`state._storage_tries`)
+///
+/// Returns
+/// -------
+/// root : bytes
+///     The 32-byte state root hash
+#[pyfunction]
+pub(crate) fn account_trie_root(
+    main_trie: &Bound<'_, PyAny>,
+    storage_tries: &Bound<'_, PyAny>,
+) -> PyResult<Py<PyBytes>> {
+    let py = main_trie.py();
+
+    let trie_data = main_trie.getattr("_data")?;
+    let trie_dict = trie_data.downcast::<PyDict>()?;
+
+    let root = AccountsTrie::from_data(trie_dict, storage_tries)?.root();
+    Ok(PyBytes::new_bound(py, root.as_slice()).into())
+}
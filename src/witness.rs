@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use alloy_primitives::{keccak256, Address, B256};
+use alloy_trie::Nibbles;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+use crate::common::{code_hash_of, encode_account, extract_account_fields, extract_address};
+use crate::trie::{AccountsTrie, StorageTrie};
+
+/// Build `address_bytes`'s storage trie retaining proof nodes for
+/// `targets`, adding every retained node into the shared, deduplicated
+/// `witness` pool keyed by `keccak256(node_rlp)`.
+fn collect_storage_witness(
+    storage_tries: &Bound<'_, PyAny>,
+    address_bytes: &Bound<'_, PyBytes>,
+    targets: Vec<Nibbles>,
+    witness: &mut HashMap<B256, Vec<u8>>,
+) -> PyResult<()> {
+    let storage_trie = StorageTrie::from_storage_tries(storage_tries, address_bytes)?;
+    let (_, proof_nodes) = storage_trie.root_with_proof(targets);
+    for (_, node) in proof_nodes.into_inner() {
+        witness.entry(keccak256(&node)).or_insert_with(|| node.to_vec());
+    }
+    Ok(())
+}
+
+/// Generate a block-execution witness: the minimal, deduplicated set of
+/// trie nodes needed to re-derive the state root for exactly the accessed
+/// accounts and storage slots.
+///
+/// Builds the account trie (and each touched account's storage trie) with
+/// `HashBuilder` exactly as `state_root` does, but installs a
+/// `ProofRetainer` over the union of accessed paths, then folds every
+/// retained node from every trie into one flat, order-independent,
+/// hash-deduplicated pool. A stateless verifier can replay the block given
+/// only this witness rather than full state.
+///
+/// Parameters
+/// ----------
+/// state : State
+///     The Python State object from ethereum.forks.{fork}.state
+/// touched_addresses : list[bytes]
+///     20-byte addresses accessed while executing the block
+/// touched_slots : dict[bytes, list[bytes]]
+///     For each address in `touched_addresses`, the 32-byte storage slots
+///     accessed while executing the block
+///
+/// Returns
+/// -------
+/// witness : dict
+///     `{"root": bytes, "nodes": list[bytes]}` — the state root and the
+///     deduplicated node pool
+#[pyfunction]
+pub(crate) fn state_witness(
+    py: Python<'_>,
+    state: &Bound<'_, PyAny>,
+    touched_addresses: Vec<Vec<u8>>,
+    touched_slots: HashMap<Vec<u8>, Vec<Vec<u8>>>,
+) -> PyResult<Py<PyDict>> {
+    let main_trie = state.getattr("_main_trie")?;
+    let storage_tries = state.getattr("_storage_tries")?;
+
+    // Check that there are no active snapshots (transactions)
+    let snapshots = state.getattr("_snapshots")?;
+    let snapshots_len: usize = snapshots.len()?;
+    if snapshots_len > 0 {
+        return Err(pyo3::exceptions::PyAssertionError::new_err(
+            "Cannot compute state root during a transaction",
+        ));
+    }
+
+    let trie_data = main_trie.getattr("_data")?;
+    let trie_dict = trie_data.downcast::<PyDict>()?;
+
+    let mut account_targets: Vec<Nibbles> = Vec::new();
+    for addr_bytes in &touched_addresses {
+        if addr_bytes.len() != 20 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "address must be 20 bytes",
+            ));
+        }
+        account_targets.push(Nibbles::unpack(keccak256(addr_bytes.as_slice())));
+    }
+
+    let mut leaves: Vec<(Address, Vec<u8>)> = Vec::new();
+    for (address_bytes, account_obj) in trie_dict.iter() {
+        if account_obj.is_none() {
+            continue;
+        }
+
+        let address_py_bytes = address_bytes.downcast::<PyBytes>()?;
+        let Some(address) = extract_address(&address_py_bytes) else {
+            continue;
+        };
+
+        let (nonce, balance, code) = extract_account_fields(&account_obj)?;
+        let storage_root = StorageTrie::from_storage_tries(&storage_tries, &address_py_bytes)?.root();
+        let code_hash = code_hash_of(&code);
+
+        leaves.push((address, encode_account(nonce, balance, storage_root, code_hash)));
+    }
+
+    let (root, account_proof_nodes) = AccountsTrie::from_leaves(leaves).root_with_proof(account_targets);
+
+    let mut witness: HashMap<B256, Vec<u8>> = HashMap::new();
+    for (_, node) in account_proof_nodes.into_inner() {
+        witness.entry(keccak256(&node)).or_insert_with(|| node.to_vec());
+    }
+
+    for addr_bytes in &touched_addresses {
+        let Some(slots) = touched_slots.get(addr_bytes) else {
+            continue;
+        };
+        if slots.is_empty() {
+            continue;
+        }
+
+        let mut addr_array = [0u8; 20];
+        addr_array.copy_from_slice(addr_bytes);
+        let address_key = PyBytes::new_bound(py, &addr_array);
+
+        let mut slot_targets = Vec::new();
+        for slot_bytes in slots {
+            if slot_bytes.len() != 32 {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "storage key must be 32 bytes",
+                ));
+            }
+            slot_targets.push(Nibbles::unpack(keccak256(slot_bytes.as_slice())));
+        }
+
+        collect_storage_witness(&storage_tries, &address_key, slot_targets, &mut witness)?;
+    }
+
+    let mut node_hashes: Vec<&B256> = witness.keys().collect();
+    node_hashes.sort();
+
+    let nodes: Vec<Py<PyBytes>> = node_hashes
+        .into_iter()
+        .map(|hash| PyBytes::new_bound(py, &witness[hash]).into())
+        .collect();
+
+    let result = PyDict::new_bound(py);
+    result.set_item("root", PyBytes::new_bound(py, root.as_slice()))?;
+    result.set_item("nodes", nodes)?;
+    Ok(result.into())
+}